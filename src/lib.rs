@@ -1,9 +1,17 @@
-use std::fs;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    time::Duration,
+};
 
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use zed_extension_api::{
-    self as zed, Command, ContextServerConfiguration, ContextServerId, Project, Result, serde_json,
+    self as zed, Command, ContextServerConfiguration, ContextServerId, Project, Result,
+    SlashCommand, SlashCommandArgumentCompletion, SlashCommandOutput, SlashCommandOutputSection,
+    Worktree,
+    http_client::{HttpMethod, HttpRequest, HttpResponse, RedirectPolicy},
+    serde_json::{self, Value, json},
     settings::ContextServerSettings,
 };
 
@@ -13,21 +21,130 @@ const PROXY_DIR: &str = "bun-docs-mcp-proxy";
 const PROXY_VERSION: &str = "v1.0.0";
 const ARCHIVE_LINUX_X64: &str = "bun-docs-mcp-proxy-linux-x86_64.tar.gz";
 const ARCHIVE_LINUX_ARM64: &str = "bun-docs-mcp-proxy-linux-aarch64.tar.gz";
+const ARCHIVE_LINUX_X64_MUSL: &str = "bun-docs-mcp-proxy-linux-x86_64-musl.tar.gz";
+const ARCHIVE_LINUX_ARM64_MUSL: &str = "bun-docs-mcp-proxy-linux-aarch64-musl.tar.gz";
 const ARCHIVE_MACOS_X64: &str = "bun-docs-mcp-proxy-macos-x86_64.tar.gz";
 const ARCHIVE_MACOS_ARM64: &str = "bun-docs-mcp-proxy-macos-aarch64.tar.gz";
 const ARCHIVE_WINDOWS_X64: &str = "bun-docs-mcp-proxy-windows-x86_64.zip";
 const ARCHIVE_WINDOWS_ARM64: &str = "bun-docs-mcp-proxy-windows-aarch64.zip";
 const BINARY_NAME_UNIX: &str = "bun-docs-mcp-proxy";
 const BINARY_NAME_WINDOWS: &str = "bun-docs-mcp-proxy.exe";
+const DEFAULT_KEEP_VERSIONS: usize = 1;
+/// Renames of a locked executable are retried this many times before giving up.
+const RENAME_ATTEMPTS: u32 = 5;
+const RENAME_RETRY_DELAY: Duration = Duration::from_millis(200);
+/// Proxy versions this extension speaks to: `>= MIN_PROXY_VERSION, < MAX_PROXY_VERSION`.
+const MIN_PROXY_VERSION: (u32, u32, u32) = (1, 0, 0);
+const MAX_PROXY_VERSION: (u32, u32, u32) = (2, 0, 0);
+const PROXY_GIT_URL: &str = "https://github.com/kjanat/bun-docs-mcp-proxy";
+const GITHUB_API_URL: &str = "https://api.github.com";
+const USER_AGENT: &str = "bun-docs-mcp-zed";
+const BUN_DOCS_MCP_URL: &str = "https://bun.com/docs/mcp";
+/// MCP protocol revision requested when slash commands open a session.
+const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+const DOCS_SLASH_COMMAND: &str = "bun-docs";
+const API_SLASH_COMMAND: &str = "bun-api";
+const SEARCH_RESULT_LIMIT: usize = 5;
+/// Well-known Bun globals and modules offered as `/bun-api` completions.
+const BUN_API_SYMBOLS: &[&str] = &[
+    "Bun.$",
+    "Bun.build",
+    "Bun.connect",
+    "Bun.CryptoHasher",
+    "Bun.deepEquals",
+    "Bun.env",
+    "Bun.escapeHTML",
+    "Bun.file",
+    "Bun.Glob",
+    "Bun.hash",
+    "Bun.inspect",
+    "Bun.listen",
+    "Bun.password",
+    "Bun.plugin",
+    "Bun.redis",
+    "Bun.s3",
+    "Bun.semver",
+    "Bun.serve",
+    "Bun.sleep",
+    "Bun.spawn",
+    "Bun.spawnSync",
+    "Bun.sql",
+    "Bun.Transpiler",
+    "Bun.udpSocket",
+    "Bun.which",
+    "Bun.write",
+    "bun:ffi",
+    "bun:jsc",
+    "bun:sqlite",
+    "bun:test",
+    "HTMLRewriter",
+];
 
+/// Settings for the `bun-docs-mcp` context server.
 #[derive(Debug, Deserialize, JsonSchema, Default)]
 struct BunDocsMcpSettings {
+    /// Absolute path to a bun-docs-mcp-proxy binary to use instead of the managed download.
     path: Option<String>,
+    /// Number of previously installed proxy versions to keep after an update (default: 1).
+    keep_versions: Option<usize>,
+    /// Run the newest previously installed proxy version instead of the pinned one.
+    #[serde(default)]
+    rollback: bool,
+    /// Base URL serving release archives as `<release_mirror>/<version>/<archive>`.
+    release_mirror: Option<String>,
+    /// Build the proxy with `cargo install` on platforms without a prebuilt binary.
+    #[serde(default)]
+    build_from_source: bool,
+    /// Environment variables set for the proxy, as name to value (e.g. `HTTPS_PROXY`,
+    /// `SSL_CERT_FILE`). The user's own environment cannot be read to pass through.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Extra arguments appended to the proxy invocation (e.g. `["--flag", "value"]`).
+    #[serde(default)]
+    extra_args: Vec<String>,
+    /// Absolute path to a bun-docs-mcp-proxy checkout whose `target/` build is used
+    /// instead of a release, for extension development.
+    dev_proxy_workspace: Option<String>,
+    /// Use a bun-docs-mcp-proxy found on PATH (e.g. from cargo or Homebrew) and skip
+    /// managed downloads; falls back to a download when none is found.
+    #[serde(default)]
+    prefer_system_binary: bool,
+    /// GitHub token sent with release lookups to raise the API rate limit.
+    github_token: Option<String>,
+    /// Set to `false` to never download the proxy and only run one placed manually
+    /// in the extension's `bun-docs-mcp-proxy/manual/` directory (default: true).
+    manage_binary: Option<bool>,
+    /// Docs MCP endpoint that `/bun-docs` and `/bun-api` query instead of
+    /// `https://bun.com/docs/mcp`, e.g. a staging deployment. The proxy itself
+    /// always uses its built-in endpoint.
+    docs_endpoint: Option<String>,
+}
+
+/// A downloadable asset of a proxy release.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct ReleaseAsset {
+    name: String,
+    #[serde(alias = "browser_download_url")]
+    download_url: String,
+}
+
+/// Release metadata persisted between sessions so lookups can be conditional.
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq)]
+struct ReleaseCache {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    assets: Vec<ReleaseAsset>,
 }
 
 struct BunDocsMcpExtension {
     cached_binary_path: Option<String>,
-    did_legacy_cleanup: bool,
+    did_startup_cleanup: bool,
+    did_version_cleanup: bool,
+    bad_versions: HashSet<String>,
+    /// `docs_endpoint` by worktree ID, recorded for every worktree of a project when
+    /// its server starts, since slash commands receive a worktree but no project to
+    /// read settings from.
+    docs_endpoints: HashMap<u64, String>,
 }
 
 fn archive_name_for(os: zed::Os, arch: zed::Architecture) -> Result<&'static str> {
@@ -44,6 +161,33 @@ fn archive_name_for(os: zed::Os, arch: zed::Architecture) -> Result<&'static str
     }
 }
 
+/// Returns the statically linked musl archive for a Linux architecture, if one is published.
+fn musl_archive_name_for(arch: zed::Architecture) -> Option<&'static str> {
+    match arch {
+        zed::Architecture::X8664 => Some(ARCHIVE_LINUX_X64_MUSL),
+        zed::Architecture::Aarch64 => Some(ARCHIVE_LINUX_ARM64_MUSL),
+        _ => None,
+    }
+}
+
+/// Checks `ldd --version` output for musl; glibc's ldd identifies as "GNU libc".
+fn is_musl_ldd_output(stdout: &[u8], stderr: &[u8]) -> bool {
+    // musl's ldd prints its banner to stderr and exits non-zero
+    [stdout, stderr]
+        .iter()
+        .any(|out| String::from_utf8_lossy(out).to_lowercase().contains("musl"))
+}
+
+/// Detects whether the Linux host uses musl libc (e.g. Alpine).
+///
+/// Falls back to glibc when `ldd` is unavailable.
+fn host_uses_musl() -> bool {
+    zed::process::Command::new("ldd")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| is_musl_ldd_output(&output.stdout, &output.stderr))
+}
+
 fn binary_name_for(os: zed::Os) -> &'static str {
     if os == zed::Os::Windows {
         BINARY_NAME_WINDOWS
@@ -61,6 +205,533 @@ fn extraction_dir(version: &str) -> String {
     format!("{PROXY_DIR}/{version}")
 }
 
+/// Staging directory that a release is extracted into before it is moved into place.
+fn staging_dir(version: &str) -> String {
+    format!("{PROXY_DIR}/{version}.partial")
+}
+
+/// Staging root for `cargo install --root`, which places binaries under `bin/`.
+fn build_dir(version: &str) -> String {
+    format!("{PROXY_DIR}/{version}.build")
+}
+
+/// Directory holding a manually installed proxy when `manage_binary` is `false`.
+fn manual_dir() -> String {
+    format!("{PROXY_DIR}/manual")
+}
+
+/// Shell commands that install the pinned proxy release into `dir` by hand.
+///
+/// `archive_name` is `None` on platforms without a prebuilt release, in which case
+/// the instructions build from source instead.
+fn manual_install_instructions(os: zed::Os, archive_name: Option<&str>, dir: &str) -> String {
+    let Some(archive_name) = archive_name else {
+        return format!(
+            "cargo install --git {PROXY_GIT_URL} --tag {PROXY_VERSION} --locked --root \"{dir}\"\n\
+             then move \"{dir}/bin/{binary}\" to \"{dir}/{binary}\"",
+            binary = binary_name_for(os)
+        );
+    };
+    let url = format!("{PROXY_GIT_URL}/releases/download/{PROXY_VERSION}/{archive_name}");
+    if os == zed::Os::Windows {
+        format!(
+            "New-Item -ItemType Directory -Force \"{dir}\"\n\
+             Invoke-WebRequest {url} -OutFile \"$env:TEMP\\{archive_name}\"\n\
+             Expand-Archive \"$env:TEMP\\{archive_name}\" -DestinationPath \"{dir}\" -Force"
+        )
+    } else {
+        format!(
+            "mkdir -p \"{dir}\"\n\
+             curl -fL {url} | tar -xz -C \"{dir}\""
+        )
+    }
+}
+
+/// Files a release archive may ship next to the binary, matched by prefix so that
+/// variants like `LICENSE-MIT` or `README.md` are accepted.
+const ARCHIVE_EXTRA_FILES: &[&str] = &["LICENSE", "README", "CHANGELOG"];
+
+/// Describes why an extracted archive entry is rejected, or `None` if it is expected.
+///
+/// Only regular files are accepted: the binary and the [`ARCHIVE_EXTRA_FILES`].
+/// Symlinks are rejected outright since they could point outside the extension
+/// work directory.
+fn archive_entry_problem(
+    name: &str,
+    is_file: bool,
+    is_symlink: bool,
+    binary_name: &str,
+) -> Option<String> {
+    let upper = name.to_ascii_uppercase();
+    if is_symlink {
+        Some(format!("{name} (symlink)"))
+    } else if !is_file {
+        Some(format!("{name} (directory)"))
+    } else if name == binary_name
+        || ARCHIVE_EXTRA_FILES
+            .iter()
+            .any(|prefix| upper.starts_with(prefix))
+    {
+        None
+    } else {
+        Some(format!("{name} (unexpected file)"))
+    }
+}
+
+/// Verifies that an extracted release directory holds only the binary and
+/// documentation files.
+///
+/// This is best-effort layout validation of what ended up in `dir`, run after Zed has
+/// extracted the archive on the host. It does not guard against path traversal:
+/// entries that escape `dir` are written by Zed's extractor before this runs.
+fn verify_archive_layout(dir: &str, binary_name: &str) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read extracted archive {dir}: {e}"))?;
+    let problems: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let file_type = entry.file_type().ok()?;
+            archive_entry_problem(
+                &name,
+                file_type.is_file(),
+                file_type.is_symlink(),
+                binary_name,
+            )
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Refusing to install archive with unexpected contents in {dir}:\n  {}",
+            problems.join("\n  ")
+        ))
+    }
+}
+
+/// Arguments for building the proxy release `version` with `cargo install`.
+///
+/// Must stay in sync with the `cargo` `process:exec` capability in `extension.toml`.
+fn cargo_install_args(version: &str, root: &str) -> Vec<String> {
+    [
+        "install",
+        "--git",
+        PROXY_GIT_URL,
+        "--tag",
+        version,
+        "--locked",
+        "--root",
+        root,
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect()
+}
+
+/// Parses a `vMAJOR.MINOR.PATCH` version directory name into comparable parts.
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.strip_prefix('v')?.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+/// Extracts the semantic version from `--version` output such as
+/// `bun-docs-mcp-proxy 1.0.0`, ignoring any pre-release or build suffix.
+fn version_from_output(output: &str) -> Option<(u32, u32, u32)> {
+    output.split_whitespace().find_map(|token| {
+        let token = token.strip_prefix('v').unwrap_or(token);
+        let core = token.split(['-', '+']).next()?;
+        parse_version(&format!("v{core}"))
+    })
+}
+
+fn is_compatible_version(version: (u32, u32, u32)) -> bool {
+    (MIN_PROXY_VERSION..MAX_PROXY_VERSION).contains(&version)
+}
+
+/// Checks that `--version` output reports a proxy version this extension supports.
+fn ensure_compatible(version_output: &str) -> Result<()> {
+    let (min, max) = (MIN_PROXY_VERSION, MAX_PROXY_VERSION);
+    let requirement = format!(
+        ">={}.{}.{}, <{}.{}.{}",
+        min.0, min.1, min.2, max.0, max.1, max.2
+    );
+    match version_from_output(version_output) {
+        Some(version) if is_compatible_version(version) => Ok(()),
+        Some((major, minor, patch)) => Err(format!(
+            "bun-docs-mcp-proxy {major}.{minor}.{patch} is not compatible with this extension (requires {requirement})"
+        )),
+        None => Err(format!(
+            "Could not determine bun-docs-mcp-proxy version from '{version_output}' (requires {requirement})"
+        )),
+    }
+}
+
+/// Returns the version directories that should be deleted so that only `current`
+/// and the `keep` newest other versions remain.
+fn versions_to_remove(installed: &[String], current: &str, keep: usize) -> Vec<String> {
+    let mut others: Vec<&String> = installed
+        .iter()
+        .filter(|v| v.as_str() != current && parse_version(v).is_some())
+        .collect();
+    others.sort_by_key(|v| std::cmp::Reverse(parse_version(v)));
+    others.into_iter().skip(keep).cloned().collect()
+}
+
+/// Returns the newest installed version older than `current`, if any.
+fn rollback_version(installed: &[String], current: &str) -> Option<String> {
+    let current = parse_version(current)?;
+    installed
+        .iter()
+        .filter(|v| parse_version(v).is_some_and(|parsed| parsed < current))
+        .max_by_key(|v| parse_version(v))
+        .cloned()
+}
+
+/// Returns the newest installed version older than `current` that this extension
+/// can still run: the same major version as `current` and within the supported range.
+fn rollback_target(installed: &[String], current: &str) -> Option<String> {
+    let major = parse_version(current)?.0;
+    let eligible: Vec<String> = installed
+        .iter()
+        .filter(|v| {
+            parse_version(v)
+                .is_some_and(|parsed| parsed.0 == major && is_compatible_version(parsed))
+        })
+        .cloned()
+        .collect();
+    rollback_version(&eligible, current)
+}
+
+/// Explains why no rollback target exists for `current`.
+fn rollback_unavailable(current: &str) -> String {
+    let is_earliest = parse_version(current)
+        .is_some_and(|version| version <= MIN_PROXY_VERSION || (version.1, version.2) == (0, 0));
+    if is_earliest {
+        format!(
+            "Cannot roll back: {current} is the earliest proxy release this extension can run, so no older version exists. Rollback becomes available once a later release is pinned"
+        )
+    } else {
+        format!(
+            "Cannot roll back: no compatible proxy version older than {current} is installed. Set 'keep_versions' to retain previous versions after updates"
+        )
+    }
+}
+
+fn is_valid_binary(path: &str) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() > 0)
+}
+
+/// Lists version directories under [`PROXY_DIR`] that contain a usable binary.
+fn installed_versions(os: zed::Os) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(PROXY_DIR) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| parse_version(name).is_some())
+        .filter(|name| is_valid_binary(&binary_rel_path(name, os)))
+        .collect()
+}
+
+/// Whether an entry in [`PROXY_DIR`] is a leftover of an interrupted install: a
+/// staging directory, a `.stale-<n>` executable moved out of a version directory
+/// while it was running, or a version directory without a usable binary.
+fn is_orphaned_entry(name: &str, has_valid_binary: bool) -> bool {
+    name.ends_with(".partial")
+        || name.ends_with(".build")
+        || name.contains(".stale-")
+        || (parse_version(name).is_some() && !has_valid_binary)
+}
+
+/// Whether an entry in [`PROXY_DIR`] is the cached release metadata of a version
+/// other than `current`, which is never looked up again.
+fn is_stale_release_cache(name: &str, current: &str) -> bool {
+    name.strip_prefix("release-")
+        .and_then(|rest| rest.strip_suffix(".json"))
+        .is_some_and(|version| version != current)
+}
+
+/// Removes staging directories and broken version directories left behind by
+/// interrupted downloads, release metadata cached for other versions, plus the
+/// pre-0.2.0 non-versioned binary.
+fn cleanup_orphaned_installs(os: zed::Os) {
+    let legacy_binary = format!("{PROXY_DIR}/{}", binary_name_for(os));
+    if fs::metadata(&legacy_binary).is_ok_and(|m| m.is_file()) {
+        let _ = fs::remove_file(&legacy_binary);
+    }
+
+    let Ok(entries) = fs::read_dir(PROXY_DIR) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let has_valid_binary = is_valid_binary(&binary_rel_path(&name, os));
+        if is_orphaned_entry(&name, has_valid_binary)
+            || is_stale_release_cache(&name, PROXY_VERSION)
+        {
+            let path = format!("{PROXY_DIR}/{name}");
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                let _ = fs::remove_dir_all(&path);
+            } else {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+/// Removes old version directories, retaining `current` plus the `keep` newest others.
+fn cleanup_old_versions(current: &str, keep: usize, os: zed::Os) {
+    let Ok(entries) = fs::read_dir(PROXY_DIR) else {
+        return;
+    };
+    let installed: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    for version in versions_to_remove(&installed, current, keep) {
+        remove_version_dir(&extraction_dir(&version), os);
+    }
+}
+
+/// Returns a free `<path>.stale-<n>` name to move a locked executable to.
+fn stale_path_for(path: &str, exists: impl Fn(&str) -> bool) -> String {
+    (0..)
+        .map(|n| format!("{path}.stale-{n}"))
+        .find(|candidate| !exists(candidate))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Whether an I/O error means the file is locked by another process.
+///
+/// Covers `ERROR_ACCESS_DENIED` (as [`std::io::ErrorKind::PermissionDenied`]) and
+/// `ERROR_SHARING_VIOLATION` / `ERROR_LOCK_VIOLATION` (raw OS errors 32 and 33).
+fn is_lock_error(err: &std::io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::PermissionDenied
+        || matches!(err.raw_os_error(), Some(32 | 33))
+}
+
+/// Renames `from` to `to`, retrying briefly while the source is locked.
+///
+/// Only used on Windows, where a file can stay locked for a moment after its
+/// process exits. Other errors, such as a missing source, fail immediately.
+fn rename_with_retry(from: &str, to: &str) -> std::io::Result<()> {
+    let mut attempt = 1;
+    loop {
+        match fs::rename(from, to) {
+            Ok(()) => return Ok(()),
+            Err(e) if is_lock_error(&e) && attempt < RENAME_ATTEMPTS => {
+                std::thread::sleep(RENAME_RETRY_DELAY);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Removes a version directory, deferring the deletion of a locked executable.
+///
+/// On Windows the executable of a proxy that is still running (e.g. in another Zed
+/// window) cannot be deleted, and neither can a directory containing it be renamed.
+/// A running executable can still be renamed, so it is moved out to a
+/// `<path>.stale-<n>` file next to the version directories, which frees the directory
+/// for removal. The startup cleanup of a later session deletes the stale file once the
+/// old process has exited.
+fn remove_version_dir(path: &str, os: zed::Os) {
+    if fs::remove_dir_all(path).is_ok() || fs::metadata(path).is_err() || os != zed::Os::Windows {
+        return;
+    }
+    let binary = format!("{path}/{}", binary_name_for(os));
+    let stale = stale_path_for(path, |candidate| fs::metadata(candidate).is_ok());
+    if rename_with_retry(&binary, &stale).is_ok() {
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+/// Builds the download URL of a release asset hosted on a mirror.
+///
+/// Mirrors are expected to lay out assets as `<base>/<version>/<archive>`, which also
+/// matches GitHub Enterprise's `https://<host>/<owner>/<repo>/releases/download` base.
+fn mirror_asset_url(base: &str, version: &str, archive_name: &str) -> Result<String> {
+    let base = base.trim().trim_end_matches('/');
+    if !(base.starts_with("https://") || base.starts_with("http://")) {
+        return Err(format!(
+            "Invalid release_mirror '{base}': expected an http:// or https:// URL"
+        ));
+    }
+    Ok(format!("{base}/{version}/{archive_name}"))
+}
+
+/// Appends proxy configuration guidance to a failed network operation.
+///
+/// Release lookups and downloads go through Zed's own HTTP client, so the extension
+/// cannot route them itself; Zed's `proxy` setting (or `HTTPS_PROXY` in the
+/// environment Zed was launched from) is what applies.
+fn with_network_hint(error: String) -> String {
+    format!(
+        "{error}\nIf you are behind an HTTP proxy, set Zed's \"proxy\" setting (e.g. \"proxy\": \"http://proxy.example.com:8080\") or launch Zed with HTTPS_PROXY set. Alternatively, use 'release_mirror' or 'path'."
+    )
+}
+
+/// Builds the proxy's environment from the `env` setting.
+fn proxy_env(settings: &BunDocsMcpSettings) -> Result<Vec<(String, String)>> {
+    settings
+        .env
+        .iter()
+        .map(|(name, value)| {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!(
+                    "Invalid environment variable name in 'env': {name:?}"
+                ));
+            }
+            Ok((name.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Validates the `docs_endpoint` setting, defaulting to [`BUN_DOCS_MCP_URL`].
+fn docs_endpoint_url(setting: Option<&str>) -> Result<String> {
+    let Some(endpoint) = setting.map(str::trim) else {
+        return Ok(BUN_DOCS_MCP_URL.to_string());
+    };
+    if !(endpoint.starts_with("https://") || endpoint.starts_with("http://")) {
+        return Err(format!(
+            "Invalid docs_endpoint '{endpoint}': expected an http:// or https:// URL"
+        ));
+    }
+    Ok(endpoint.to_string())
+}
+
+/// Candidate locations of a locally built proxy inside a development workspace,
+/// in order of preference.
+fn dev_binary_candidates(workspace: &str, os: zed::Os) -> Vec<String> {
+    let workspace = workspace.trim_end_matches(['/', '\\']);
+    let binary_name = binary_name_for(os);
+    ["", "proxy/"]
+        .iter()
+        .flat_map(|prefix| {
+            ["release", "debug"]
+                .iter()
+                .map(move |profile| format!("{workspace}/{prefix}target/{profile}/{binary_name}"))
+        })
+        .collect()
+}
+
+fn release_cache_path(version: &str) -> String {
+    format!("{PROXY_DIR}/release-{version}.json")
+}
+
+/// Returns the first value of a response header, matched case-insensitively.
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Builds the request headers for a release lookup, made conditional on the cached
+/// response and authenticated when a token is configured.
+fn release_request_headers(
+    cache: Option<&ReleaseCache>,
+    token: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut headers = vec![
+        (
+            "Accept".to_string(),
+            "application/vnd.github+json".to_string(),
+        ),
+        ("User-Agent".to_string(), USER_AGENT.to_string()),
+    ];
+    if let Some(token) = token.map(str::trim).filter(|token| !token.is_empty()) {
+        headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+    }
+    if let Some(cache) = cache {
+        if let Some(etag) = &cache.etag {
+            headers.push(("If-None-Match".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &cache.last_modified {
+            headers.push(("If-Modified-Since".to_string(), last_modified.clone()));
+        }
+    }
+    headers
+}
+
+/// Turns a GitHub release response into a cache entry.
+///
+/// An empty body is a `304 Not Modified`, in which case the cached entry is reused.
+fn release_from_response(
+    headers: &[(String, String)],
+    body: &[u8],
+    cached: Option<ReleaseCache>,
+) -> Result<ReleaseCache> {
+    if body.is_empty() {
+        return cached.ok_or_else(|| "GitHub returned an empty release response".to_string());
+    }
+
+    #[derive(Deserialize)]
+    struct GithubRelease {
+        assets: Vec<ReleaseAsset>,
+    }
+    let release: GithubRelease = serde_json::from_slice(body)
+        .map_err(|e| format!("Invalid GitHub release response: {e}"))?;
+    Ok(ReleaseCache {
+        etag: header_value(headers, "etag").map(ToString::to_string),
+        last_modified: header_value(headers, "last-modified").map(ToString::to_string),
+        assets: release.assets,
+    })
+}
+
+/// Looks up the assets of a proxy release, using a conditional request against the
+/// cached response.
+///
+/// Only called when the pinned release has to be downloaded. GitHub only exempts
+/// `304 Not Modified` from the rate limit for authenticated requests, so for
+/// anonymous lookups the cache mainly serves as a fallback for re-downloads once
+/// rate-limited.
+fn fetch_release(version: &str, token: Option<&str>) -> Result<ReleaseCache> {
+    let cache_path = release_cache_path(version);
+    let cached: Option<ReleaseCache> = fs::read(&cache_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+    let url = format!("{GITHUB_API_URL}/repos/{PROXY_REPO}/releases/tags/{version}");
+    let response = HttpRequest::builder()
+        .method(HttpMethod::Get)
+        .url(&url)
+        .headers(release_request_headers(cached.as_ref(), token))
+        .redirect_policy(RedirectPolicy::FollowLimit(5))
+        .build()?
+        .fetch();
+
+    let release = match (response, cached) {
+        (Ok(response), cached) => release_from_response(&response.headers, &response.body, cached)?,
+        // Rate limited or offline: a previously fetched release is still accurate
+        (Err(_), Some(cached)) => return Ok(cached),
+        (Err(e), None) => {
+            return Err(with_network_hint(format!(
+                "Failed to get release {version} from {PROXY_REPO}: {e}"
+            )));
+        }
+    };
+
+    if fs::create_dir_all(PROXY_DIR).is_ok()
+        && let Ok(json) = serde_json::to_vec(&release)
+    {
+        let _ = fs::write(&cache_path, json);
+    }
+    Ok(release)
+}
+
 fn expand_tilde(path: &str) -> Result<String> {
     if path.starts_with("~/") {
         // WASM sandbox doesn't have access to HOME/USERPROFILE env vars
@@ -72,41 +743,172 @@ fn expand_tilde(path: &str) -> Result<String> {
     Ok(path.to_string())
 }
 
+/// Resolves a path relative to the extension work directory into an absolute one.
+///
+/// Processes spawned through `zed::process::Command` resolve relative paths against
+/// Zed's own working directory, not the extension work directory.
+fn work_dir_path(rel_path: &str) -> String {
+    std::env::current_dir()
+        .map(|dir| dir.join(rel_path).to_string_lossy().into_owned())
+        .unwrap_or_else(|_| rel_path.to_string())
+}
+
 /// Validates a user-provided binary path by executing it with `--version`.
 ///
 /// Checks that:
 /// 1. The binary can be executed
 /// 2. It exits successfully (code 0)
 /// 3. The output contains "bun-docs-mcp-proxy" (verifies it's our binary)
+/// 4. The reported version is within the supported range
 fn validate_user_binary(path: &str) -> Result<()> {
+    ensure_compatible(&check_installation(path, "custom binary")?)
+}
+
+/// Outcome of a single installation health check.
+struct HealthCheck {
+    name: &'static str,
+    /// `None` when the check was skipped, otherwise its detail or failure reason.
+    result: Option<Result<String>>,
+}
+
+/// Renders health checks as a multi-line status report.
+fn format_health_report(checks: &[HealthCheck]) -> String {
+    let mut report = String::from("bun-docs-mcp installation check failed:");
+    for check in checks {
+        let (status, detail) = match &check.result {
+            Some(Ok(detail)) => ("ok", detail.as_str()),
+            Some(Err(reason)) => ("FAIL", reason.as_str()),
+            None => ("skip", "not run"),
+        };
+        report.push_str(&format!("\n  [{status:^4}] {}: {detail}", check.name));
+    }
+    report
+}
+
+/// The checks reported by [`check_installation`], in the order they run.
+fn installation_checks(
+    presence: Option<Result<String>>,
+    version: Option<Result<String>>,
+) -> [HealthCheck; 2] {
+    [
+        HealthCheck {
+            name: "binary present",
+            result: presence,
+        },
+        HealthCheck {
+            name: "responds to --version",
+            result: version,
+        },
+    ]
+}
+
+/// Why a binary failed its `--version` probe.
+enum ProbeFailure {
+    /// The binary could not be spawned (e.g. `process:exec` not granted, or the file
+    /// is briefly held by antivirus), so nothing is known about it.
+    NotRun(String),
+    /// The binary is missing, or it ran and exited unsuccessfully or is not the proxy.
+    Failed(String),
+}
+
+impl ProbeFailure {
+    fn into_message(self) -> String {
+        match self {
+            ProbeFailure::NotRun(message) | ProbeFailure::Failed(message) => message,
+        }
+    }
+}
+
+/// Verifies that the binary exists, is executable, and identifies as the proxy.
+///
+/// Relative paths are resolved against the extension work directory. Returns the
+/// `--version` output, or a multi-line report describing which check failed.
+///
+/// The check stops at `--version`: the proxy is never started as a server, so an
+/// MCP handshake failure is not detected here. Extensions cannot write to a
+/// spawned process's stdin.
+fn check_installation(path: &str, label: &str) -> Result<String> {
+    probe_installation(path, label).map_err(ProbeFailure::into_message)
+}
+
+/// Like [`check_installation`], but tells a binary that ran and failed apart from
+/// one that could not be run at all.
+fn probe_installation(path: &str, label: &str) -> std::result::Result<String, ProbeFailure> {
+    // Only the work directory is visible to the sandbox, so absolute paths can
+    // only be checked by running them
+    let presence = if std::path::Path::new(path).is_absolute() {
+        None
+    } else {
+        Some(match fs::metadata(path) {
+            Ok(m) if m.is_file() && m.len() > 0 => Ok(format!("{path} ({} bytes)", m.len())),
+            Ok(_) => Err(format!("{path} is not a file or is empty")),
+            Err(e) => Err(format!("{path}: {e}")),
+        })
+    };
+    let presence_failed = presence.as_ref().is_some_and(|r| r.is_err());
+    let probe = (!presence_failed).then(|| run_version(&work_dir_path(path), label));
+    let not_run = matches!(probe, Some(Err(ProbeFailure::NotRun(_))));
+    let version = probe.map(|result| result.map_err(ProbeFailure::into_message));
+
+    match version {
+        Some(Ok(version)) => Ok(version),
+        _ => {
+            let report = format_health_report(&installation_checks(presence, version));
+            Err(if not_run {
+                ProbeFailure::NotRun(report)
+            } else {
+                ProbeFailure::Failed(report)
+            })
+        }
+    }
+}
+
+/// Runs `<path> --version` and checks that it identifies as bun-docs-mcp-proxy.
+///
+/// `label` describes the binary in error messages (e.g. "custom binary"). Returns the
+/// trimmed `--version` output.
+fn validate_binary(path: &str, label: &str) -> Result<String> {
+    run_version(path, label).map_err(ProbeFailure::into_message)
+}
+
+/// [`validate_binary`], keeping apart a binary that could not be spawned.
+fn run_version(path: &str, label: &str) -> std::result::Result<String, ProbeFailure> {
     let output = zed::process::Command::new(path)
         .arg("--version")
         .output()
-        .map_err(|e| format!("Failed to execute custom binary at {path}: {e}"))?;
+        .map_err(|e| ProbeFailure::NotRun(format!("Failed to execute {label} at {path}: {e}")))?;
 
     match output.status {
         Some(0) => {
             // Verify it's actually our binary by checking the output
             let stdout = String::from_utf8_lossy(&output.stdout);
             if !stdout.contains("bun-docs-mcp-proxy") {
-                return Err(format!(
+                return Err(ProbeFailure::Failed(format!(
                     "Binary at {path} is not bun-docs-mcp-proxy (output: {stdout})"
-                ));
+                )));
             }
-            Ok(())
+            Ok(stdout.trim().to_string())
         }
-        Some(code) => Err(format!(
-            "Custom binary at {path} exited with code {code}. stderr: {}",
+        Some(code) => Err(ProbeFailure::Failed(format!(
+            "The {label} at {path} exited with code {code}. stderr: {}",
             String::from_utf8_lossy(&output.stderr)
-        )),
-        None => Err(format!("Custom binary at {path} was terminated by signal")),
+        ))),
+        None => Err(ProbeFailure::Failed(format!(
+            "The {label} at {path} was terminated by signal"
+        ))),
     }
 }
 
 impl BunDocsMcpExtension {
     fn get_platform_archive_name() -> Result<&'static str> {
         let (os, arch) = zed::current_platform();
-        archive_name_for(os, arch)
+        let archive_name = archive_name_for(os, arch)?;
+        if os == zed::Os::Linux && host_uses_musl() {
+            return musl_archive_name_for(arch).ok_or_else(|| {
+                format!("No musl build of bun-docs-mcp-proxy is published for {arch:?}")
+            });
+        }
+        Ok(archive_name)
     }
 
     fn get_binary_rel_path() -> String {
@@ -114,22 +916,123 @@ impl BunDocsMcpExtension {
         binary_rel_path(PROXY_VERSION, os)
     }
 
-    fn ensure_binary(&mut self) -> Result<String> {
-        if !self.did_legacy_cleanup {
-            self.did_legacy_cleanup = true;
-            let (os, _) = zed::current_platform();
-            let legacy_binary = format!("{PROXY_DIR}/{}", binary_name_for(os));
-            if fs::metadata(&legacy_binary)
-                .map(|m| m.is_file())
-                .unwrap_or(false)
-            {
-                let _ = fs::remove_file(&legacy_binary);
+    /// Resolves the binary of the newest retained version older than [`PROXY_VERSION`].
+    fn rollback_binary() -> Result<String> {
+        let (os, _) = zed::current_platform();
+        let version = rollback_target(&installed_versions(os), PROXY_VERSION)
+            .ok_or_else(|| rollback_unavailable(PROXY_VERSION))?;
+        Ok(binary_rel_path(&version, os))
+    }
+
+    /// Resolves the first working proxy build in a development workspace.
+    fn dev_binary(workspace: &str) -> Result<String> {
+        let workspace = expand_tilde(workspace)?;
+        let (os, _) = zed::current_platform();
+        let candidates = dev_binary_candidates(&workspace, os);
+        candidates
+            .iter()
+            .find(|candidate| validate_binary(candidate, "workspace build").is_ok())
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "No working bun-docs-mcp-proxy build found in {workspace}. Run `cargo build` there first. Looked for:\n  {}",
+                    candidates.join("\n  ")
+                )
+            })
+    }
+
+    /// Resolves the manually installed proxy used when `manage_binary` is `false`,
+    /// without any network access.
+    fn manual_binary() -> Result<String> {
+        let (os, _) = zed::current_platform();
+        let binary_path = format!("{}/{}", manual_dir(), binary_name_for(os));
+        if !is_valid_binary(&binary_path) {
+            let archive_name = Self::get_platform_archive_name().ok();
+            return Err(format!(
+                "'manage_binary' is false and no proxy is installed at {}. Install bun-docs-mcp-proxy {PROXY_VERSION} there with:\n\n{}\n\nor set 'path' to an existing binary.",
+                work_dir_path(&binary_path),
+                manual_install_instructions(os, archive_name, &work_dir_path(&manual_dir()))
+            ));
+        }
+        check_installation(&binary_path, "manual binary")
+            .and_then(|version| ensure_compatible(&version))?;
+        Ok(binary_path)
+    }
+
+    /// Picks the proxy binary according to settings, in order of precedence: custom
+    /// `path`, development workspace, system binary, manual install, rollback,
+    /// managed download.
+    fn resolve_binary(&mut self, settings: &BunDocsMcpSettings) -> Result<String> {
+        if let Some(path) = settings.path.as_ref() {
+            let expanded = expand_tilde(path)?;
+            if expanded.trim().is_empty() {
+                return Err(
+                    "Custom binary path is empty - remove 'path' setting or provide a valid path"
+                        .to_string(),
+                );
             }
+            validate_user_binary(&expanded)?;
+            return Ok(expanded);
+        }
+
+        if let Some(workspace) = settings.dev_proxy_workspace.as_deref() {
+            return Self::dev_binary(workspace);
+        }
+
+        if settings.prefer_system_binary
+            && let Some(system) = Self::system_binary()
+        {
+            return Ok(system);
+        }
+
+        if settings.manage_binary == Some(false) {
+            return Self::manual_binary();
+        }
+
+        if settings.rollback {
+            return Self::rollback_binary();
+        }
+
+        self.ensure_binary(settings)
+    }
+
+    /// Returns the proxy installed on PATH, if it runs, identifies correctly, and is
+    /// a compatible version.
+    fn system_binary() -> Option<String> {
+        let (os, _) = zed::current_platform();
+        let binary_name = binary_name_for(os);
+        validate_binary(binary_name, "system binary")
+            .ok()
+            .filter(|version| ensure_compatible(version).is_ok())
+            .map(|_| binary_name.to_string())
+    }
+
+    /// Deletes superseded version directories once per session.
+    fn cleanup_versions_once(&mut self, keep: usize) {
+        if !self.did_version_cleanup {
+            self.did_version_cleanup = true;
+            let (os, _) = zed::current_platform();
+            cleanup_old_versions(PROXY_VERSION, keep, os);
+        }
+    }
+
+    fn ensure_binary(&mut self, settings: &BunDocsMcpSettings) -> Result<String> {
+        let keep_versions = settings.keep_versions.unwrap_or(DEFAULT_KEEP_VERSIONS);
+
+        // A release that failed to start earlier this session is not retried
+        if self.bad_versions.contains(PROXY_VERSION) {
+            return Self::rollback_binary();
+        }
+
+        if !self.did_startup_cleanup {
+            self.did_startup_cleanup = true;
+            let (os, _) = zed::current_platform();
+            cleanup_orphaned_installs(os);
         }
 
         // Re-validate cached path in case user deleted the binary while Zed was running
         if let Some(cached) = &self.cached_binary_path {
-            if fs::metadata(cached).is_ok_and(|m| m.is_file() && m.len() > 0) {
+            if is_valid_binary(cached) {
                 return Ok(cached.clone());
             }
             self.cached_binary_path = None;
@@ -139,6 +1042,17 @@ impl BunDocsMcpExtension {
 
         match fs::metadata(&binary_path) {
             Ok(metadata) if metadata.is_file() && metadata.len() > 0 => {
+                // Checked once per session, before the path is cached. A failing install
+                // is kept on disk: deleting it over a start-up probe would only force a
+                // re-download next session
+                match probe_installation(&binary_path, "installed binary") {
+                    Ok(_) => {}
+                    Err(ProbeFailure::NotRun(report)) => return Err(report),
+                    Err(ProbeFailure::Failed(report)) => {
+                        return self.reject_pinned_version(report);
+                    }
+                }
+                self.cleanup_versions_once(keep_versions);
                 self.cached_binary_path = Some(binary_path.clone());
                 return Ok(binary_path);
             }
@@ -153,62 +1067,464 @@ impl BunDocsMcpExtension {
             Err(e) => return Err(format!("Failed to check binary at {binary_path}: {e}")),
         }
 
-        let release = zed::github_release_by_tag_name(PROXY_REPO, PROXY_VERSION)
-            .map_err(|e| format!("Failed to get release {PROXY_VERSION} from {PROXY_REPO}: {e}"))?;
+        match Self::get_platform_archive_name() {
+            Ok(archive_name) => Self::download_binary(&binary_path, archive_name, settings)?,
+            Err(_) if settings.build_from_source => Self::build_binary(&binary_path)?,
+            Err(e) => {
+                return Err(format!(
+                    "{e}\nAlternatively, set \"build_from_source\": true to build the proxy locally with cargo."
+                ));
+            }
+        }
 
-        let archive_name = Self::get_platform_archive_name()?;
-        let asset = release
-            .assets
-            .iter()
-            .find(|asset| asset.name == archive_name)
-            .ok_or_else(|| {
-                format!(
-                    "No {} asset found in release {} for {}",
-                    archive_name, release.version, PROXY_REPO
-                )
-            })?;
+        // Make sure the fresh download actually runs before committing to it. Only a
+        // binary that ran and failed is discarded; a spawn error says nothing about it
+        match probe_installation(&binary_path, "downloaded binary") {
+            Ok(_) => {}
+            Err(ProbeFailure::NotRun(report)) => return Err(report),
+            Err(ProbeFailure::Failed(report)) => {
+                let (os, _) = zed::current_platform();
+                remove_version_dir(&extraction_dir(PROXY_VERSION), os);
+                return self.reject_pinned_version(report);
+            }
+        }
 
-        let archive_path = std::path::Path::new(archive_name);
-        let file_type = if archive_path
-            .extension()
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
-        {
-            zed::DownloadedFileType::Zip
-        } else if archive_name.ends_with(".tar.gz") {
-            // .tar.gz is a compound extension, check as string
-            zed::DownloadedFileType::GzipTar
-        } else {
-            zed::DownloadedFileType::Uncompressed
-        };
+        self.cleanup_versions_once(keep_versions);
+        self.cached_binary_path = Some(binary_path.clone());
+        Ok(binary_path)
+    }
 
-        let extract_dir = extraction_dir(PROXY_VERSION);
-        zed::download_file(&asset.download_url, &extract_dir, file_type).map_err(|e| {
+    /// Handles a pinned release whose binary ran but failed its `--version` probe:
+    /// marks it bad for the session and falls back to a retained previous version.
+    fn reject_pinned_version(&mut self, probe_err: String) -> Result<String> {
+        self.bad_versions.insert(PROXY_VERSION.to_string());
+        Self::rollback_binary().map_err(|rollback_err| {
+            let dir = extraction_dir(PROXY_VERSION);
+            let reinstall_hint = if fs::metadata(&dir).is_ok() {
+                format!("\nDelete {} to download it again.", work_dir_path(&dir))
+            } else {
+                String::new()
+            };
             format!(
-                "Failed to download {} from {}: {}",
-                archive_name, asset.download_url, e
+                "Proxy {PROXY_VERSION} failed to start and no previous version is available: {rollback_err}\n{probe_err}{reinstall_hint}"
             )
-        })?;
+        })
+    }
 
-        match fs::metadata(&binary_path) {
+    /// Resolves the download URL of the platform archive, either from the GitHub
+    /// release or from a configured mirror.
+    fn asset_download_url(archive_name: &str, settings: &BunDocsMcpSettings) -> Result<String> {
+        if let Some(mirror) = settings.release_mirror.as_deref() {
+            return mirror_asset_url(mirror, PROXY_VERSION, archive_name);
+        }
+
+        fetch_release(PROXY_VERSION, settings.github_token.as_deref())?
+            .assets
+            .into_iter()
+            .find(|asset| asset.name == archive_name)
+            .map(|asset| asset.download_url)
+            .ok_or_else(|| {
+                format!("No {archive_name} asset found in release {PROXY_VERSION} for {PROXY_REPO}")
+            })
+    }
+
+    /// Builds the pinned proxy release with `cargo install` for platforms without a
+    /// prebuilt archive, then moves the binary to `binary_path`.
+    fn build_binary(binary_path: &str) -> Result<()> {
+        let (os, _) = zed::current_platform();
+        let build_root = build_dir(PROXY_VERSION);
+        let _ = fs::remove_dir_all(&build_root);
+        fs::create_dir_all(extraction_dir(PROXY_VERSION))
+            .map_err(|e| format!("Failed to create {}: {e}", extraction_dir(PROXY_VERSION)))?;
+
+        let output = zed::process::Command::new("cargo")
+            .args(cargo_install_args(
+                PROXY_VERSION,
+                &work_dir_path(&build_root),
+            ))
+            .output()
+            .map_err(|e| format!("Failed to run cargo (is Rust installed and on PATH?): {e}"))?;
+        if output.status != Some(0) {
+            let _ = fs::remove_dir_all(&build_root);
+            return Err(format!(
+                "Building bun-docs-mcp-proxy {PROXY_VERSION} from source failed. stderr: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let built_binary = format!("{build_root}/bin/{}", binary_name_for(os));
+        let moved = fs::rename(&built_binary, binary_path);
+        let _ = fs::remove_dir_all(&build_root);
+        moved.map_err(|e| format!("Failed to move {built_binary} to {binary_path}: {e}"))
+    }
+
+    /// Downloads and extracts the pinned proxy release so that `binary_path` exists.
+    fn download_binary(
+        binary_path: &str,
+        archive_name: &str,
+        settings: &BunDocsMcpSettings,
+    ) -> Result<()> {
+        let download_url =
+            Self::asset_download_url(archive_name, settings).map_err(|e| {
+                if archive_name.ends_with("-musl.tar.gz") {
+                    format!(
+                        "{e}\nThis system uses musl libc (e.g. Alpine), where the glibc build fails to start with a misleading 'not found' error. Build bun-docs-mcp-proxy for musl and set 'path' to it."
+                    )
+                } else {
+                    e
+                }
+            })?;
+
+        let archive_path = std::path::Path::new(archive_name);
+        let file_type = if archive_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        {
+            zed::DownloadedFileType::Zip
+        } else if archive_name.ends_with(".tar.gz") {
+            // .tar.gz is a compound extension, check as string
+            zed::DownloadedFileType::GzipTar
+        } else {
+            zed::DownloadedFileType::Uncompressed
+        };
+
+        // Extract into a staging directory so an interrupted download never leaves a
+        // half-populated version directory behind
+        let staging = staging_dir(PROXY_VERSION);
+        let _ = fs::remove_dir_all(&staging);
+        zed::download_file(&download_url, &staging, file_type).map_err(|e| {
+            with_network_hint(format!(
+                "Failed to download {archive_name} from {download_url}: {e}"
+            ))
+        })?;
+
+        let (os, _) = zed::current_platform();
+        if let Err(e) = verify_archive_layout(&staging, binary_name_for(os)) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        let staged_binary = format!("{staging}/{}", binary_name_for(os));
+        match fs::metadata(&staged_binary) {
             Ok(metadata) if metadata.is_file() && metadata.len() > 0 => {}
             Ok(_) => {
+                let _ = fs::remove_dir_all(&staging);
                 return Err(format!(
-                    "Extracted binary is invalid (not a file or empty): {binary_path}"
+                    "Extracted binary is invalid (not a file or empty): {staged_binary}"
                 ));
             }
             Err(_) => {
-                return Err(format!("Binary not found after extraction: {binary_path}"));
+                let _ = fs::remove_dir_all(&staging);
+                return Err(format!(
+                    "Binary not found after extraction: {staged_binary}"
+                ));
             }
         }
 
-        let (os, _) = zed::current_platform();
+        let extract_dir = extraction_dir(PROXY_VERSION);
+        remove_version_dir(&extract_dir, os);
+        fs::rename(&staging, &extract_dir)
+            .map_err(|e| format!("Failed to move {staging} to {extract_dir}: {e}"))?;
+
         if os != zed::Os::Windows {
-            zed::make_file_executable(&binary_path)
+            zed::make_file_executable(binary_path)
                 .map_err(|e| format!("Failed to make {binary_path} executable: {e}"))?;
         }
 
-        self.cached_binary_path = Some(binary_path.clone());
-        Ok(binary_path)
+        Ok(())
+    }
+}
+
+/// Extracts the JSON-RPC response from a Streamable HTTP body, which is either plain
+/// JSON or an SSE stream whose `data:` lines carry JSON-RPC messages.
+fn parse_mcp_response(body: &str) -> Result<Value> {
+    let body = body.trim_start();
+    let response = if body.starts_with('{') {
+        serde_json::from_str(body).map_err(|e| format!("Invalid JSON-RPC response: {e}"))?
+    } else {
+        body.lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+            .find(|message| message.get("result").is_some() || message.get("error").is_some())
+            .ok_or_else(|| "No JSON-RPC response found in event stream".to_string())?
+    };
+
+    if let Some(error) = response.get("error") {
+        let message = error["message"].as_str().unwrap_or("unknown error");
+        return Err(format!("Bun docs request failed: {message}"));
+    }
+    Ok(response["result"].clone())
+}
+
+/// Parameters of the `initialize` request that opens a slash command session.
+fn initialize_params() -> Value {
+    json!({
+        "protocolVersion": MCP_PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": {"name": USER_AGENT, "version": env!("CARGO_PKG_VERSION")},
+    })
+}
+
+/// Builds the headers of a Streamable HTTP request, echoing the session ID and
+/// protocol revision once the server has assigned them.
+fn mcp_request_headers(
+    session_id: Option<&str>,
+    protocol_version: Option<&str>,
+) -> Vec<(String, String)> {
+    let mut headers = vec![
+        ("Content-Type".to_string(), "application/json".to_string()),
+        (
+            "Accept".to_string(),
+            "application/json, text/event-stream".to_string(),
+        ),
+        ("User-Agent".to_string(), USER_AGENT.to_string()),
+    ];
+    if let Some(session_id) = session_id {
+        headers.push(("Mcp-Session-Id".to_string(), session_id.to_string()));
+    }
+    if let Some(protocol_version) = protocol_version {
+        headers.push((
+            "MCP-Protocol-Version".to_string(),
+            protocol_version.to_string(),
+        ));
+    }
+    headers
+}
+
+/// A Streamable HTTP session with a docs MCP endpoint.
+///
+/// Slash commands cannot talk to the running context server, so they open their
+/// own session with the endpoint the proxy bridges to, through Zed's HTTP client.
+struct McpSession {
+    endpoint: String,
+    session_id: Option<String>,
+    protocol_version: Option<String>,
+    next_id: u64,
+}
+
+impl McpSession {
+    /// Performs the `initialize` handshake, keeping the `Mcp-Session-Id` the server
+    /// assigns (stateless servers assign none).
+    fn connect(endpoint: &str) -> Result<Self> {
+        let mut session = Self {
+            endpoint: endpoint.to_string(),
+            session_id: None,
+            protocol_version: None,
+            next_id: 1,
+        };
+        let initialize = session.message("initialize", initialize_params());
+        let response = session.post(&initialize)?;
+        let result = parse_mcp_response(&String::from_utf8_lossy(&response.body))?;
+        session.session_id =
+            header_value(&response.headers, "mcp-session-id").map(ToString::to_string);
+        session.protocol_version = result["protocolVersion"].as_str().map(ToString::to_string);
+        session.post(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))?;
+        Ok(session)
+    }
+
+    /// Sends a JSON-RPC request within the session and returns its result.
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let message = self.message(method, params);
+        let response = self.post(&message)?;
+        parse_mcp_response(&String::from_utf8_lossy(&response.body))
+    }
+
+    /// Builds a JSON-RPC request with the next request ID.
+    fn message(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn post(&self, message: &Value) -> Result<HttpResponse> {
+        HttpRequest::builder()
+            .method(HttpMethod::Post)
+            .url(&self.endpoint)
+            .headers(mcp_request_headers(
+                self.session_id.as_deref(),
+                self.protocol_version.as_deref(),
+            ))
+            .body(message.to_string())
+            .build()?
+            .fetch()
+            .map_err(|e| format!("Failed to reach {}: {e}", self.endpoint))
+    }
+}
+
+/// Finds the docs search tool in a `tools/list` result along with its query argument.
+fn find_search_tool(tools: &Value) -> Option<(String, String)> {
+    let tool = tools["tools"].as_array()?.iter().find(|tool| {
+        tool["name"]
+            .as_str()
+            .is_some_and(|n| n.to_lowercase().contains("search"))
+    })?;
+    let argument = tool["inputSchema"]["required"]
+        .as_array()
+        .and_then(|required| required.first())
+        .and_then(Value::as_str)
+        .unwrap_or("query");
+    Some((tool["name"].as_str()?.to_string(), argument.to_string()))
+}
+
+/// Returns the non-empty text items of a `tools/call` result.
+fn tool_result_items(result: &Value) -> Vec<&str> {
+    result["content"]
+        .as_array()
+        .map(|content| {
+            content
+                .iter()
+                .filter_map(|item| item["text"].as_str())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Joins the text content of a `tools/call` result, keeping at most `limit` items.
+fn tool_result_text(result: &Value, limit: usize) -> String {
+    tool_result_items(result)
+        .into_iter()
+        .take(limit)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Picks the first search result that mentions `symbol`, if any.
+fn best_symbol_match<'a>(results: &[&'a str], symbol: &str) -> Option<&'a str> {
+    let needle = symbol.to_lowercase();
+    results
+        .iter()
+        .find(|result| result.to_lowercase().contains(&needle))
+        .copied()
+}
+
+/// Spells `symbol` the way the docs do, since it is matched case-insensitively but
+/// signatures and examples are searched verbatim: a known symbol's spelling, else
+/// its first occurrence in `section`.
+fn canonical_symbol(symbol: &str, section: &str) -> String {
+    if let Some(known) = BUN_API_SYMBOLS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(symbol))
+    {
+        return known.to_string();
+    }
+    section
+        .to_ascii_lowercase()
+        .find(&symbol.to_ascii_lowercase())
+        .map_or_else(
+            || symbol.to_string(),
+            |start| section[start..start + symbol.len()].to_string(),
+        )
+}
+
+/// Returns the bodies of the fenced code blocks in a markdown section.
+fn code_blocks(section: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in section.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(body) => blocks.push(body.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(body) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    blocks
+}
+
+/// Finds the line declaring `symbol`: a typed call signature for globals such as
+/// `Bun.serve(options: Serve): Server`, or the import of a module such as
+/// `import { Database } from "bun:sqlite"`.
+fn symbol_signature<'a>(section: &'a str, symbol: &str) -> Option<&'a str> {
+    let call = [format!("{symbol}("), format!("{symbol}<")];
+    let import = [format!("from \"{symbol}\""), format!("from '{symbol}'")];
+    section.lines().map(str::trim).find(|line| {
+        (call.iter().any(|c| line.contains(c.as_str())) && line.contains("):"))
+            || import.iter().any(|i| line.contains(i.as_str()))
+    })
+}
+
+/// Formats the reference for `symbol` from the search result that mentions it: its
+/// signature plus the first example using it, or the whole result when neither is
+/// found.
+fn format_api_reference(symbol: &str, section: &str) -> String {
+    let signature = symbol_signature(section, symbol);
+    let example = code_blocks(section)
+        .into_iter()
+        .find(|block| block.contains(symbol) && Some(block.trim()) != signature);
+
+    let mut reference = format!("## `{symbol}`\n\n");
+    if signature.is_none() && example.is_none() {
+        reference.push_str(section);
+        return reference;
+    }
+    if let Some(signature) = signature {
+        reference.push_str(&format!("```ts\n{signature}\n```\n"));
+    }
+    if let Some(example) = example {
+        reference.push_str(&format!("\n### Example\n\n```ts\n{example}\n```\n"));
+    }
+    reference
+}
+
+/// Returns the known Bun API symbols matching a partially typed argument.
+fn complete_api_symbol(partial: &str) -> Vec<&'static str> {
+    let partial = partial.to_lowercase();
+    BUN_API_SYMBOLS
+        .iter()
+        .filter(|symbol| symbol.to_lowercase().contains(&partial))
+        .copied()
+        .collect()
+}
+
+/// Calls the docs search tool at `endpoint` and returns the raw `tools/call` result.
+fn call_search_tool(endpoint: &str, query: &str) -> Result<Value> {
+    let mut session = McpSession::connect(endpoint)?;
+    let tools = session.request("tools/list", json!({}))?;
+    let (tool, argument) = find_search_tool(&tools)
+        .ok_or_else(|| "The Bun docs MCP server does not expose a search tool".to_string())?;
+    let result = session.request(
+        "tools/call",
+        json!({"name": tool, "arguments": {argument: query}}),
+    )?;
+    if result["isError"].as_bool() == Some(true) {
+        return Err(format!(
+            "Bun docs search failed: {}",
+            tool_result_text(&result, usize::MAX)
+        ));
+    }
+    Ok(result)
+}
+
+/// Searches the Bun docs and returns up to `limit` results as text.
+fn search_docs(endpoint: &str, query: &str, limit: usize) -> Result<String> {
+    Ok(tool_result_text(&call_search_tool(endpoint, query)?, limit))
+}
+
+/// Resolves a Bun API symbol to the reference section describing it.
+fn lookup_api_symbol(endpoint: &str, symbol: &str) -> Result<String> {
+    let result = call_search_tool(endpoint, symbol)?;
+    let section = best_symbol_match(&tool_result_items(&result), symbol).ok_or_else(|| {
+        format!(
+            "No Bun API reference mentions `{symbol}`. Try /{DOCS_SLASH_COMMAND} {symbol} for a broader search."
+        )
+    })?;
+    Ok(format_api_reference(
+        &canonical_symbol(symbol, section),
+        section,
+    ))
+}
+
+/// Wraps `text` as slash command output with a single labelled section.
+fn slash_command_output(text: String, label: String) -> SlashCommandOutput {
+    SlashCommandOutput {
+        sections: vec![SlashCommandOutputSection {
+            range: (0..text.len()).into(),
+            label,
+        }],
+        text,
     }
 }
 
@@ -216,7 +1532,66 @@ impl zed::Extension for BunDocsMcpExtension {
     fn new() -> Self {
         Self {
             cached_binary_path: None,
-            did_legacy_cleanup: false,
+            did_startup_cleanup: false,
+            did_version_cleanup: false,
+            bad_versions: HashSet::new(),
+            docs_endpoints: HashMap::new(),
+        }
+    }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<SlashCommandArgumentCompletion>> {
+        match command.name.as_str() {
+            API_SLASH_COMMAND => Ok(complete_api_symbol(&args.join(" "))
+                .into_iter()
+                .map(|symbol| SlashCommandArgumentCompletion {
+                    label: symbol.to_string(),
+                    new_text: symbol.to_string(),
+                    run_command: true,
+                })
+                .collect()),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn run_slash_command(
+        &self,
+        command: SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&Worktree>,
+    ) -> Result<SlashCommandOutput> {
+        let setting = worktree.and_then(|worktree| self.docs_endpoints.get(&worktree.id()));
+        let endpoint = docs_endpoint_url(setting.map(String::as_str))?;
+        match command.name.as_str() {
+            DOCS_SLASH_COMMAND => {
+                let query = args.join(" ");
+                let query = query.trim();
+                if query.is_empty() {
+                    return Err(format!("Usage: /{DOCS_SLASH_COMMAND} <query>"));
+                }
+
+                let results = search_docs(&endpoint, query, SEARCH_RESULT_LIMIT)?;
+                if results.is_empty() {
+                    return Err(format!("No Bun docs results for \"{query}\""));
+                }
+                Ok(slash_command_output(results, format!("Bun docs: {query}")))
+            }
+            API_SLASH_COMMAND => {
+                let symbol = args.join(" ");
+                let symbol = symbol.trim();
+                if symbol.is_empty() {
+                    return Err(format!("Usage: /{API_SLASH_COMMAND} <symbol>"));
+                }
+
+                Ok(slash_command_output(
+                    lookup_api_symbol(&endpoint, symbol)?,
+                    format!("Bun API: {symbol}"),
+                ))
+            }
+            name => Err(format!("Unknown slash command: {name}")),
         }
     }
 
@@ -227,38 +1602,35 @@ impl zed::Extension for BunDocsMcpExtension {
     ) -> Result<Command> {
         match context_server_id.as_ref() {
             CONTEXT_SERVER_ID => {
+                // Resolves the project's `.zed/settings.json` on top of the user settings, so
+                // each workspace can use its own settings
                 let settings = ContextServerSettings::for_project(CONTEXT_SERVER_ID, project)
                     .map_err(|e| format!("Failed to load context server settings: {e}"))?;
 
-                let custom_settings: Option<BunDocsMcpSettings> = if let Some(ref value) =
-                    settings.settings
+                let custom_settings: BunDocsMcpSettings = if let Some(ref value) = settings.settings
                 {
-                    Some(
-                        serde_json::from_value(value.clone())
-                            .map_err(|e| format!("Invalid {CONTEXT_SERVER_ID} settings: {e}"))?,
-                    )
+                    serde_json::from_value(value.clone())
+                        .map_err(|e| format!("Invalid {CONTEXT_SERVER_ID} settings: {e}"))?
                 } else {
-                    None
+                    BunDocsMcpSettings::default()
                 };
 
-                let binary_path = match custom_settings.as_ref().and_then(|s| s.path.as_ref()) {
-                    Some(path) => {
-                        let expanded = expand_tilde(path)?;
-                        if expanded.trim().is_empty() {
-                            return Err(
-                                "Custom binary path is empty - remove 'path' setting or provide a valid path".to_string()
-                            );
+                for worktree_id in project.worktree_ids() {
+                    match &custom_settings.docs_endpoint {
+                        Some(endpoint) => {
+                            self.docs_endpoints.insert(worktree_id, endpoint.clone());
+                        }
+                        None => {
+                            self.docs_endpoints.remove(&worktree_id);
                         }
-                        validate_user_binary(&expanded)?;
-                        expanded
                     }
-                    None => self.ensure_binary()?,
-                };
+                }
+                let binary_path = self.resolve_binary(&custom_settings)?;
 
                 Ok(Command {
                     command: binary_path,
-                    args: vec![],
-                    env: vec![],
+                    args: custom_settings.extra_args.clone(),
+                    env: proxy_env(&custom_settings)?,
                 })
             }
             id => Err(format!("Unknown context server: {id}")),
@@ -326,6 +1698,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_musl_archive_name_for() {
+        assert_eq!(
+            musl_archive_name_for(zed::Architecture::X8664),
+            Some(ARCHIVE_LINUX_X64_MUSL)
+        );
+        assert_eq!(
+            musl_archive_name_for(zed::Architecture::Aarch64),
+            Some(ARCHIVE_LINUX_ARM64_MUSL)
+        );
+        assert_eq!(musl_archive_name_for(zed::Architecture::X86), None);
+    }
+
+    #[test]
+    fn test_is_musl_ldd_output() {
+        // musl ldd writes its banner to stderr
+        assert!(is_musl_ldd_output(
+            b"",
+            b"musl libc (x86_64)\nVersion 1.2.4\n"
+        ));
+        assert!(!is_musl_ldd_output(
+            b"ldd (GNU libc) 2.39\nCopyright (C) 2024 Free Software Foundation, Inc.\n",
+            b""
+        ));
+        assert!(!is_musl_ldd_output(b"", b""));
+    }
+
     #[test]
     fn test_archive_name_for_unsupported() {
         let result = archive_name_for(zed::Os::Linux, zed::Architecture::X86);
@@ -362,6 +1761,145 @@ mod tests {
         assert_eq!(extraction_dir("v1.0.0"), "bun-docs-mcp-proxy/v1.0.0");
     }
 
+    #[test]
+    fn test_staging_dir() {
+        assert_eq!(staging_dir("v1.0.0"), "bun-docs-mcp-proxy/v1.0.0.partial");
+        assert_eq!(parse_version("v1.0.0.partial"), None);
+    }
+
+    #[test]
+    fn test_is_orphaned_entry() {
+        // Staging leftovers are always orphaned
+        assert!(is_orphaned_entry("v1.0.0.partial", false));
+        assert!(is_orphaned_entry("v1.0.0.build", true));
+        // Executables moved out while running are swept once the process exits
+        assert!(is_orphaned_entry("v1.0.0.stale-0", true));
+
+        // Version directories are orphaned only without a usable binary
+        assert!(is_orphaned_entry("v1.0.0", false));
+        assert!(!is_orphaned_entry("v1.0.0", true));
+
+        // Unrelated entries are left alone
+        assert!(!is_orphaned_entry("notes.txt", false));
+        assert!(!is_orphaned_entry("bun-docs-mcp-proxy", false));
+    }
+
+    #[test]
+    fn test_archive_entry_problem() {
+        let binary = BINARY_NAME_UNIX;
+        assert_eq!(archive_entry_problem(binary, true, false, binary), None);
+        assert_eq!(archive_entry_problem("LICENSE", true, false, binary), None);
+        assert_eq!(
+            archive_entry_problem("LICENSE-MIT", true, false, binary),
+            None
+        );
+        assert_eq!(
+            archive_entry_problem("README.md", true, false, binary),
+            None
+        );
+        assert_eq!(
+            archive_entry_problem("CHANGELOG.md", true, false, binary),
+            None
+        );
+
+        // A symlinked binary could point anywhere on disk
+        assert_eq!(
+            archive_entry_problem(binary, false, true, binary),
+            Some(format!("{binary} (symlink)"))
+        );
+        assert_eq!(
+            archive_entry_problem("lib", false, false, binary),
+            Some("lib (directory)".to_string())
+        );
+        assert_eq!(
+            archive_entry_problem("install.sh", true, false, binary),
+            Some("install.sh (unexpected file)".to_string())
+        );
+        // The Windows binary name is not accepted on Unix
+        assert!(archive_entry_problem(BINARY_NAME_WINDOWS, true, false, binary).is_some());
+    }
+
+    #[test]
+    fn test_manual_install_instructions() {
+        let unix = manual_install_instructions(
+            zed::Os::Linux,
+            Some(ARCHIVE_LINUX_X64),
+            "/work/bun-docs-mcp-proxy/manual",
+        );
+        assert!(unix.contains("mkdir -p \"/work/bun-docs-mcp-proxy/manual\""));
+        assert!(unix.contains(&format!(
+            "curl -fL https://github.com/kjanat/bun-docs-mcp-proxy/releases/download/{PROXY_VERSION}/{ARCHIVE_LINUX_X64} | tar -xz"
+        )));
+
+        let windows = manual_install_instructions(
+            zed::Os::Windows,
+            Some(ARCHIVE_WINDOWS_X64),
+            "C:\\work\\manual",
+        );
+        assert!(windows.contains("Invoke-WebRequest"));
+        assert!(windows.contains("Expand-Archive"));
+        assert!(!windows.contains("curl"));
+
+        // Platforms without a release archive fall back to a source build
+        let source = manual_install_instructions(zed::Os::Linux, None, "/work/manual");
+        assert!(source.starts_with("cargo install --git"));
+        assert!(source.contains("/work/manual/bin/bun-docs-mcp-proxy"));
+
+        // The manual directory is never swept as an orphaned install
+        assert!(!is_orphaned_entry("manual", false));
+    }
+
+    #[test]
+    fn test_is_lock_error() {
+        assert!(is_lock_error(&std::io::ErrorKind::PermissionDenied.into()));
+        assert!(is_lock_error(&std::io::Error::from_raw_os_error(32)));
+        assert!(!is_lock_error(&std::io::ErrorKind::NotFound.into()));
+    }
+
+    #[test]
+    fn test_stale_path_for() {
+        let base = "bun-docs-mcp-proxy/v1.0.0";
+        assert_eq!(
+            stale_path_for(base, |_| false),
+            "bun-docs-mcp-proxy/v1.0.0.stale-0"
+        );
+        // Earlier leftovers that are still locked are skipped
+        let taken = [
+            "bun-docs-mcp-proxy/v1.0.0.stale-0",
+            "bun-docs-mcp-proxy/v1.0.0.stale-1",
+        ];
+        assert_eq!(
+            stale_path_for(base, |c| taken.contains(&c)),
+            "bun-docs-mcp-proxy/v1.0.0.stale-2"
+        );
+        // Stale executables never count as installed versions
+        assert!(parse_version("v1.0.0.stale-0").is_none());
+    }
+
+    #[test]
+    fn test_build_dir() {
+        assert_eq!(build_dir("v1.0.0"), "bun-docs-mcp-proxy/v1.0.0.build");
+        // Build staging must never be mistaken for an installed version
+        assert_eq!(parse_version("v1.0.0.build"), None);
+    }
+
+    #[test]
+    fn test_cargo_install_args() {
+        assert_eq!(
+            cargo_install_args("v1.0.0", "/work/bun-docs-mcp-proxy/v1.0.0.build"),
+            [
+                "install",
+                "--git",
+                "https://github.com/kjanat/bun-docs-mcp-proxy",
+                "--tag",
+                "v1.0.0",
+                "--locked",
+                "--root",
+                "/work/bun-docs-mcp-proxy/v1.0.0.build",
+            ]
+        );
+    }
+
     #[test]
     fn test_archive_extensions_valid() {
         let archives = [
@@ -371,6 +1909,8 @@ mod tests {
             ARCHIVE_MACOS_ARM64,
             ARCHIVE_WINDOWS_X64,
             ARCHIVE_WINDOWS_ARM64,
+            ARCHIVE_LINUX_X64_MUSL,
+            ARCHIVE_LINUX_ARM64_MUSL,
         ];
 
         for archive in archives {
@@ -420,6 +1960,17 @@ mod tests {
         assert!(json.contains("path"));
         // Should NOT contain nested "command" anymore
         assert!(!json.contains("command"));
+
+        // Every setting is documented for Zed's settings UI
+        let value = serde_json::to_value(&schema).unwrap();
+        let properties = value["properties"].as_object().unwrap();
+        assert!(!properties.is_empty());
+        for (name, property) in properties {
+            assert!(
+                property.get("description").is_some_and(|d| d.is_string()),
+                "Setting '{name}' should have a description"
+            );
+        }
     }
 
     #[test]
@@ -448,6 +1999,385 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_proxy_env() {
+        let json = r#"{"env": {"SSL_CERT_FILE": "/etc/ssl/corp.pem", "HTTPS_PROXY": "http://proxy:3128"}}"#;
+        let settings: BunDocsMcpSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            proxy_env(&settings).unwrap(),
+            vec![
+                ("HTTPS_PROXY".to_string(), "http://proxy:3128".to_string()),
+                ("SSL_CERT_FILE".to_string(), "/etc/ssl/corp.pem".to_string()),
+            ]
+        );
+
+        // No env configured
+        let settings: BunDocsMcpSettings = serde_json::from_str("{}").unwrap();
+        assert!(proxy_env(&settings).unwrap().is_empty());
+
+        // Invalid names are rejected
+        for name in ["", "A=B", "NUL\0"] {
+            let settings = BunDocsMcpSettings {
+                env: BTreeMap::from([(name.to_string(), "x".to_string())]),
+                ..Default::default()
+            };
+            assert!(proxy_env(&settings).unwrap_err().contains("'env'"));
+        }
+
+        // Values must be strings
+        let json = r#"{"env": {"RUST_LOG": 1}}"#;
+        assert!(serde_json::from_str::<BunDocsMcpSettings>(json).is_err());
+    }
+
+    #[test]
+    fn test_settings_extra_args() {
+        let json = r#"{"extra_args": ["--flag", "value"]}"#;
+        let settings: BunDocsMcpSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.extra_args, ["--flag", "value"]);
+
+        let settings: BunDocsMcpSettings = serde_json::from_str("{}").unwrap();
+        assert!(settings.extra_args.is_empty());
+
+        let json = r#"{"extra_args": "--flag value"}"#;
+        assert!(serde_json::from_str::<BunDocsMcpSettings>(json).is_err());
+    }
+
+    #[test]
+    fn test_mcp_session_handshake() {
+        let params = initialize_params();
+        assert_eq!(params["protocolVersion"], MCP_PROTOCOL_VERSION);
+        assert_eq!(params["clientInfo"]["name"], USER_AGENT);
+
+        // Before initialize completes, no session headers are sent
+        let headers = mcp_request_headers(None, None);
+        assert_eq!(header_value(&headers, "Mcp-Session-Id"), None);
+        assert_eq!(
+            header_value(&headers, "Accept"),
+            Some("application/json, text/event-stream")
+        );
+
+        let headers = mcp_request_headers(Some("abc123"), Some("2025-03-26"));
+        assert_eq!(header_value(&headers, "mcp-session-id"), Some("abc123"));
+        assert_eq!(
+            header_value(&headers, "mcp-protocol-version"),
+            Some("2025-03-26")
+        );
+    }
+
+    #[test]
+    fn test_docs_endpoint_url() {
+        assert_eq!(docs_endpoint_url(None).unwrap(), BUN_DOCS_MCP_URL);
+        assert_eq!(
+            docs_endpoint_url(Some(" https://staging.example.com/docs/mcp ")).unwrap(),
+            "https://staging.example.com/docs/mcp"
+        );
+        assert!(
+            docs_endpoint_url(Some("staging.example.com"))
+                .unwrap_err()
+                .contains("docs_endpoint")
+        );
+    }
+
+    #[test]
+    fn test_parse_mcp_response() {
+        // Plain JSON body
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":{"tools":[]}}"#;
+        assert_eq!(parse_mcp_response(body).unwrap(), json!({"tools": []}));
+
+        // SSE body with a leading notification
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\nevent: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":true}}\n\n";
+        assert_eq!(parse_mcp_response(body).unwrap(), json!({"ok": true}));
+
+        // JSON-RPC errors are surfaced
+        let body =
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found"}}"#;
+        assert!(
+            parse_mcp_response(body)
+                .unwrap_err()
+                .contains("Method not found")
+        );
+
+        // Streams without a response are rejected
+        assert!(parse_mcp_response("event: ping\n\n").is_err());
+    }
+
+    #[test]
+    fn test_find_search_tool() {
+        let tools = json!({"tools": [
+            {"name": "get_page", "inputSchema": {"required": ["path"]}},
+            {"name": "SearchBun", "inputSchema": {"required": ["query"]}},
+        ]});
+        assert_eq!(
+            find_search_tool(&tools),
+            Some(("SearchBun".to_string(), "query".to_string()))
+        );
+
+        // Falls back to "query" when no required argument is declared
+        let tools = json!({"tools": [{"name": "search_docs", "inputSchema": {}}]});
+        assert_eq!(
+            find_search_tool(&tools),
+            Some(("search_docs".to_string(), "query".to_string()))
+        );
+
+        assert_eq!(find_search_tool(&json!({"tools": []})), None);
+        assert_eq!(find_search_tool(&json!({})), None);
+    }
+
+    #[test]
+    fn test_tool_result_text() {
+        let result = json!({"content": [
+            {"type": "text", "text": "First result\n"},
+            {"type": "image", "data": "..."},
+            {"type": "text", "text": "   "},
+            {"type": "text", "text": "Second result"},
+            {"type": "text", "text": "Third result"},
+        ]});
+        assert_eq!(
+            tool_result_text(&result, 2),
+            "First result\n\nSecond result"
+        );
+        assert_eq!(tool_result_text(&json!({}), 5), "");
+    }
+
+    #[test]
+    fn test_best_symbol_match() {
+        let results = [
+            "# Bun.file\nLazily load files",
+            "# HTTP server\n`Bun.serve({ fetch })` starts a server",
+        ];
+        assert_eq!(best_symbol_match(&results, "bun.serve"), Some(results[1]));
+        // Unrelated results are never passed off as the symbol's reference
+        assert_eq!(best_symbol_match(&results, "bun:ffi"), None);
+        assert_eq!(best_symbol_match(&[], "Bun.serve"), None);
+    }
+
+    #[test]
+    fn test_format_api_reference() {
+        let section = "# HTTP server\n\n```ts\nBun.serve(options: Serve): Server\n```\n\nStart a server:\n\n```ts\nconst server = Bun.serve({\n  fetch(req) {\n    return new Response(\"Hi\");\n  },\n});\n```\n";
+        let reference = format_api_reference("Bun.serve", section);
+        assert!(
+            reference
+                .starts_with("## `Bun.serve`\n\n```ts\nBun.serve(options: Serve): Server\n```")
+        );
+        assert!(reference.contains("### Example\n\n```ts\nconst server = Bun.serve({"));
+        assert!(!reference.contains("Start a server"));
+
+        // Lower-case input is matched against the docs' spelling
+        assert_eq!(canonical_symbol("bun.serve", section), "Bun.serve");
+        assert_eq!(
+            format_api_reference(&canonical_symbol("bun.serve", section), section),
+            reference
+        );
+        assert_eq!(
+            canonical_symbol("server", "Returns a `Server` instance."),
+            "Server"
+        );
+        assert_eq!(canonical_symbol("bun.nope", section), "bun.nope");
+
+        let section = "SQLite driver.\n\n```ts\nimport { Database } from \"bun:sqlite\";\nconst db = new Database(\":memory:\");\n```";
+        assert_eq!(
+            symbol_signature(section, "bun:sqlite"),
+            Some("import { Database } from \"bun:sqlite\";")
+        );
+
+        // Without a signature or example, the matching result is shown as is
+        let section = "`Bun.sleep` pauses for a number of milliseconds.";
+        assert_eq!(
+            format_api_reference("Bun.sleep", section),
+            format!("## `Bun.sleep`\n\n{section}")
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        assert_eq!(
+            code_blocks("text\n```ts\na\nb\n```\nmore\n```\nc\n```"),
+            vec!["a\nb", "c"]
+        );
+        // An unterminated block is ignored
+        assert!(code_blocks("```ts\nnever closed").is_empty());
+    }
+
+    #[test]
+    fn test_complete_api_symbol() {
+        assert_eq!(complete_api_symbol("spawn"), ["Bun.spawn", "Bun.spawnSync"]);
+        assert_eq!(complete_api_symbol("BUN:SQL"), ["bun:sqlite"]);
+        assert_eq!(complete_api_symbol("").len(), BUN_API_SYMBOLS.len());
+        assert!(complete_api_symbol("nonexistent").is_empty());
+    }
+
+    #[test]
+    fn test_slash_command_output() {
+        let output = slash_command_output("results".to_string(), "Bun docs: serve".to_string());
+        assert_eq!(output.text, "results");
+        assert_eq!(output.sections.len(), 1);
+        assert_eq!(output.sections[0].range.start, 0);
+        assert_eq!(output.sections[0].range.end, 7);
+        assert_eq!(output.sections[0].label, "Bun docs: serve");
+    }
+
+    #[test]
+    fn test_format_health_report() {
+        let report = format_health_report(&installation_checks(
+            Some(Ok(
+                "bun-docs-mcp-proxy/v1.0.0/bun-docs-mcp-proxy".to_string()
+            )),
+            Some(Err("exited with code 126".to_string())),
+        ));
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "bun-docs-mcp installation check failed:");
+        assert_eq!(
+            lines[1],
+            "  [ ok ] binary present: bun-docs-mcp-proxy/v1.0.0/bun-docs-mcp-proxy"
+        );
+        assert_eq!(
+            lines[2],
+            "  [FAIL] responds to --version: exited with code 126"
+        );
+
+        // A missing binary is never executed
+        let report = format_health_report(&installation_checks(
+            Some(Err(
+                "bun-docs-mcp-proxy/v1.0.0/bun-docs-mcp-proxy: not found".to_string(),
+            )),
+            None,
+        ));
+        assert_eq!(
+            report.lines().last(),
+            Some("  [skip] responds to --version: not run")
+        );
+    }
+
+    #[test]
+    fn test_check_installation_missing_binary() {
+        let err = check_installation("bun-docs-mcp-proxy/v0.0.0/missing", "installed binary")
+            .unwrap_err();
+        assert!(err.starts_with("bun-docs-mcp installation check failed:"));
+        assert!(err.contains("[FAIL] binary present"));
+        assert!(err.contains("[skip] responds to --version"));
+
+        // A missing binary is a real failure, not a probe that could not run
+        assert!(matches!(
+            probe_installation("bun-docs-mcp-proxy/v0.0.0/missing", "installed binary"),
+            Err(ProbeFailure::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn test_settings_binary_sources() {
+        let json = r#"{"prefer_system_binary": true, "dev_proxy_workspace": "/src/proxy"}"#;
+        let settings: BunDocsMcpSettings = serde_json::from_str(json).unwrap();
+        assert!(settings.prefer_system_binary);
+        assert_eq!(settings.dev_proxy_workspace.as_deref(), Some("/src/proxy"));
+
+        let settings: BunDocsMcpSettings = serde_json::from_str("{}").unwrap();
+        assert!(!settings.prefer_system_binary);
+        assert_eq!(settings.dev_proxy_workspace, None);
+    }
+
+    #[test]
+    fn test_dev_binary_candidates() {
+        assert_eq!(
+            dev_binary_candidates("/src/bun-docs-mcp-proxy/", zed::Os::Linux),
+            [
+                "/src/bun-docs-mcp-proxy/target/release/bun-docs-mcp-proxy",
+                "/src/bun-docs-mcp-proxy/target/debug/bun-docs-mcp-proxy",
+                "/src/bun-docs-mcp-proxy/proxy/target/release/bun-docs-mcp-proxy",
+                "/src/bun-docs-mcp-proxy/proxy/target/debug/bun-docs-mcp-proxy",
+            ]
+        );
+        assert_eq!(
+            dev_binary_candidates("C:/src/proxy", zed::Os::Windows)[0],
+            "C:/src/proxy/target/release/bun-docs-mcp-proxy.exe"
+        );
+    }
+
+    #[test]
+    fn test_header_value() {
+        let headers = vec![
+            ("ETag".to_string(), "W/\"abc\"".to_string()),
+            ("content-type".to_string(), "application/json".to_string()),
+        ];
+        assert_eq!(header_value(&headers, "etag"), Some("W/\"abc\""));
+        assert_eq!(
+            header_value(&headers, "Content-Type"),
+            Some("application/json")
+        );
+        assert_eq!(header_value(&headers, "last-modified"), None);
+    }
+
+    #[test]
+    fn test_release_request_headers() {
+        let headers = release_request_headers(None, None);
+        assert_eq!(header_value(&headers, "user-agent"), Some(USER_AGENT));
+        assert_eq!(header_value(&headers, "if-none-match"), None);
+        assert_eq!(header_value(&headers, "authorization"), None);
+
+        let headers = release_request_headers(None, Some(" ghp_example "));
+        assert_eq!(
+            header_value(&headers, "authorization"),
+            Some("Bearer ghp_example")
+        );
+        // Blank tokens are ignored
+        let headers = release_request_headers(None, Some("  "));
+        assert_eq!(header_value(&headers, "authorization"), None);
+
+        let cache = ReleaseCache {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some("Sat, 24 Jan 2026 10:00:00 GMT".to_string()),
+            assets: vec![],
+        };
+        let headers = release_request_headers(Some(&cache), None);
+        assert_eq!(header_value(&headers, "if-none-match"), Some("\"abc\""));
+        assert_eq!(
+            header_value(&headers, "if-modified-since"),
+            Some("Sat, 24 Jan 2026 10:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn test_release_from_response() {
+        let body = br#"{"tag_name": "v1.0.0", "assets": [
+            {"name": "bun-docs-mcp-proxy-linux-x86_64.tar.gz",
+             "browser_download_url": "https://github.com/kjanat/bun-docs-mcp-proxy/releases/download/v1.0.0/bun-docs-mcp-proxy-linux-x86_64.tar.gz",
+             "size": 1300000}
+        ]}"#;
+        let headers = vec![("etag".to_string(), "\"abc\"".to_string())];
+        let release = release_from_response(&headers, body, None).unwrap();
+        assert_eq!(release.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(release.last_modified, None);
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(release.assets[0].name, ARCHIVE_LINUX_X64);
+        assert!(release.assets[0].download_url.ends_with(ARCHIVE_LINUX_X64));
+
+        // Cache entries round-trip through their serialized form
+        let json = serde_json::to_vec(&release).unwrap();
+        let restored: ReleaseCache = serde_json::from_slice(&json).unwrap();
+        assert_eq!(restored, release);
+
+        // 304 Not Modified (empty body) reuses the cache
+        let reused = release_from_response(&[], b"", Some(restored)).unwrap();
+        assert_eq!(reused, release);
+        assert!(release_from_response(&[], b"", None).is_err());
+
+        assert!(release_from_response(&[], b"not json", None).is_err());
+    }
+
+    #[test]
+    fn test_release_cache_path() {
+        assert_eq!(
+            release_cache_path("v1.0.0"),
+            "bun-docs-mcp-proxy/release-v1.0.0.json"
+        );
+        assert!(!is_orphaned_entry("release-v1.0.0.json", false));
+
+        // Only caches of other versions are pruned
+        assert!(is_stale_release_cache("release-v0.9.0.json", "v1.0.0"));
+        assert!(!is_stale_release_cache("release-v1.0.0.json", "v1.0.0"));
+        assert!(!is_stale_release_cache("v0.9.0", "v1.0.0"));
+    }
+
     #[test]
     fn test_expand_tilde() {
         // Non-tilde paths pass through unchanged
@@ -464,4 +2394,169 @@ mod tests {
         assert!(err.contains("not supported"));
         assert!(err.contains("absolute path"));
     }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v1.0.0"), Some((1, 0, 0)));
+        assert_eq!(parse_version("v0.12.3"), Some((0, 12, 3)));
+        assert_eq!(parse_version("1.0.0"), None);
+        assert_eq!(parse_version("v1.0"), None);
+        assert_eq!(parse_version("v1.0.0.1"), None);
+        assert_eq!(parse_version("v1.x.0"), None);
+    }
+
+    #[test]
+    fn test_version_from_output() {
+        assert_eq!(
+            version_from_output("bun-docs-mcp-proxy 1.0.0\n"),
+            Some((1, 0, 0))
+        );
+        assert_eq!(
+            version_from_output("bun-docs-mcp-proxy v1.2.3"),
+            Some((1, 2, 3))
+        );
+        assert_eq!(
+            version_from_output("bun-docs-mcp-proxy 2.0.0-beta.1+abc"),
+            Some((2, 0, 0))
+        );
+        assert_eq!(version_from_output("bun-docs-mcp-proxy"), None);
+    }
+
+    #[test]
+    fn test_ensure_compatible() {
+        assert!(ensure_compatible("bun-docs-mcp-proxy 1.0.0").is_ok());
+        assert!(ensure_compatible("bun-docs-mcp-proxy 1.9.42").is_ok());
+
+        let err = ensure_compatible("bun-docs-mcp-proxy 0.3.0").unwrap_err();
+        assert!(err.contains("0.3.0 is not compatible"));
+        assert!(err.contains(">=1.0.0, <2.0.0"));
+        assert!(ensure_compatible("bun-docs-mcp-proxy 2.0.0").is_err());
+        assert!(ensure_compatible("bun-docs-mcp-proxy dev").is_err());
+    }
+
+    #[test]
+    fn test_pinned_version_is_compatible() {
+        assert!(parse_version(PROXY_VERSION).is_some_and(is_compatible_version));
+    }
+
+    #[test]
+    fn test_versions_to_remove() {
+        let installed: Vec<String> = ["v0.2.0", "v1.0.0", "v0.3.0", "v0.10.0", "staging"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        // Keep the current version plus the single newest previous one
+        let mut removed = versions_to_remove(&installed, "v1.0.0", 1);
+        removed.sort();
+        assert_eq!(removed, vec!["v0.2.0", "v0.3.0"]);
+
+        // Keeping zero removes every other version
+        let mut removed = versions_to_remove(&installed, "v1.0.0", 0);
+        removed.sort();
+        assert_eq!(removed, vec!["v0.10.0", "v0.2.0", "v0.3.0"]);
+
+        // Keeping more than installed removes nothing
+        assert!(versions_to_remove(&installed, "v1.0.0", 5).is_empty());
+    }
+
+    #[test]
+    fn test_rollback_version() {
+        let installed: Vec<String> = ["v0.2.0", "v1.0.0", "v0.10.0", "v1.1.0"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            rollback_version(&installed, "v1.0.0"),
+            Some("v0.10.0".to_string())
+        );
+        assert_eq!(rollback_version(&installed, "v0.2.0"), None);
+        assert_eq!(rollback_version(&[], "v1.0.0"), None);
+    }
+
+    #[test]
+    fn test_rollback_target_with_pinned_version() {
+        // The pin is the first 1.x release: pre-1.0 versions are incompatible, so
+        // nothing can be rolled back to, and keep_versions would not help.
+        // Revisit when PROXY_VERSION moves.
+        let installed: Vec<String> = ["v0.9.0", "v0.10.0", PROXY_VERSION]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(rollback_target(&installed, PROXY_VERSION), None);
+        let error = rollback_unavailable(PROXY_VERSION);
+        assert!(error.contains("earliest proxy release"));
+        assert!(!error.contains("keep_versions"));
+
+        // A later pin in the same major rolls back to the newest retained release
+        let installed: Vec<String> = ["v0.9.0", "v1.0.0", "v1.0.1", "v1.1.0"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            rollback_target(&installed, "v1.1.0"),
+            Some("v1.0.1".to_string())
+        );
+        assert!(rollback_unavailable("v1.1.0").contains("keep_versions"));
+        // Crossing a major version is never a rollback target
+        assert_eq!(rollback_target(&["v1.4.0".to_string()], "v2.0.0"), None);
+    }
+
+    #[test]
+    fn test_settings_rollback_fields() {
+        let json = r#"{"keep_versions": 3, "rollback": true}"#;
+        let settings: BunDocsMcpSettings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.keep_versions, Some(3));
+        assert!(settings.rollback);
+
+        let settings: BunDocsMcpSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.keep_versions, None);
+        assert!(!settings.rollback);
+
+        let json = r#"{"keep_versions": -1}"#;
+        assert!(serde_json::from_str::<BunDocsMcpSettings>(json).is_err());
+    }
+
+    #[test]
+    fn test_work_dir_path() {
+        let path = work_dir_path("bun-docs-mcp-proxy/v1.0.0/bun-docs-mcp-proxy");
+        assert!(std::path::Path::new(&path).is_absolute());
+        assert!(path.ends_with("bun-docs-mcp-proxy"));
+    }
+
+    #[test]
+    fn test_mirror_asset_url() {
+        assert_eq!(
+            mirror_asset_url(
+                "https://mirror.example.com/proxy",
+                "v1.0.0",
+                ARCHIVE_LINUX_X64
+            )
+            .unwrap(),
+            "https://mirror.example.com/proxy/v1.0.0/bun-docs-mcp-proxy-linux-x86_64.tar.gz"
+        );
+
+        // Trailing slashes and whitespace are ignored
+        assert_eq!(
+            mirror_asset_url(
+                " https://ghe.example.com/kjanat/bun-docs-mcp-proxy/releases/download/ ",
+                "v1.0.0",
+                ARCHIVE_WINDOWS_X64
+            )
+            .unwrap(),
+            "https://ghe.example.com/kjanat/bun-docs-mcp-proxy/releases/download/v1.0.0/bun-docs-mcp-proxy-windows-x86_64.zip"
+        );
+
+        let err =
+            mirror_asset_url("ftp://mirror.example.com", "v1.0.0", ARCHIVE_LINUX_X64).unwrap_err();
+        assert!(err.contains("release_mirror"));
+    }
+
+    #[test]
+    fn test_with_network_hint() {
+        let msg = with_network_hint("Failed to download archive: timed out".to_string());
+        assert!(msg.starts_with("Failed to download archive: timed out\n"));
+        assert!(msg.contains("\"proxy\""));
+        assert!(msg.contains("HTTPS_PROXY"));
+    }
 }